@@ -31,6 +31,10 @@ pub struct General {
     pub fade: f32,
     #[serde(default = "default_brightness")]
     pub brightness: Brightness,
+    /// Pause LED control while the logind session is inactive, and re-assert it after resuming
+    /// from suspend. Requires a running systemd-logind.
+    #[serde(default)]
+    pub session_integration: bool,
 }
 
 impl Default for General {
@@ -39,6 +43,7 @@ impl Default for General {
             timeout: default_timeout(),
             fade: default_fade(),
             brightness: default_brightness(),
+            session_integration: false,
         }
     }
 }
@@ -62,6 +67,8 @@ pub struct Input {
 pub struct Led {
     pub name: String,
     pub brightness: Option<Brightness>,
+    /// Target color for RGB/multi-channel backlights, as a `"#rrggbb"` hex string or `[r, g, b]`.
+    pub color: Option<Color>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -91,6 +98,39 @@ impl FromStr for Brightness {
     }
 }
 
+/// An RGB color, used to tint `multi_intensity`-capable keyboard backlights.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "ColorRepr")]
+pub struct Color(pub [u8; 3]);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Hex(String),
+    Rgb([u8; 3]),
+}
+
+impl TryFrom<ColorRepr> for Color {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: ColorRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ColorRepr::Rgb(rgb) => Ok(Self(rgb)),
+            ColorRepr::Hex(hex) => {
+                let hex = hex.trim_start_matches('#');
+                ensure!(
+                    hex.len() == 6,
+                    "color must be a 6-digit hex string, got '{hex}'"
+                );
+                let channel = |i: usize| -> anyhow::Result<u8> {
+                    Ok(u8::from_str_radix(&hex[i..i + 2], 16)?)
+                };
+                Ok(Self([channel(0)?, channel(2)?, channel(4)?]))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,4 +144,34 @@ mod tests {
     fn empty_config_parses() {
         Config::parse("").unwrap();
     }
+
+    fn parse_color(s: &str) -> anyhow::Result<Color> {
+        let led: Led = toml::from_str(&format!("name = \"x\"\ncolor = {s}\n"))?;
+        Ok(led.color.unwrap())
+    }
+
+    #[test]
+    fn color_hex_with_hash() {
+        assert_eq!(parse_color("\"#ff8000\"").unwrap(), Color([0xff, 0x80, 0x00]));
+    }
+
+    #[test]
+    fn color_hex_without_hash() {
+        assert_eq!(parse_color("\"ff8000\"").unwrap(), Color([0xff, 0x80, 0x00]));
+    }
+
+    #[test]
+    fn color_rgb_array() {
+        assert_eq!(parse_color("[255, 128, 0]").unwrap(), Color([0xff, 0x80, 0x00]));
+    }
+
+    #[test]
+    fn color_hex_wrong_length_errors() {
+        assert!(parse_color("\"ff80\"").is_err());
+    }
+
+    #[test]
+    fn color_hex_invalid_digits_errors() {
+        assert!(parse_color("\"zzzzzz\"").is_err());
+    }
 }
@@ -1,7 +1,10 @@
 //! Opens matching `evdev` devices and receives input events.
 
 use std::{
-    io, process,
+    collections::HashMap,
+    io,
+    os::fd::{AsRawFd, OwnedFd, RawFd},
+    process,
     sync::{
         Arc,
         mpsc::{self, RecvTimeoutError, SyncSender, sync_channel},
@@ -15,6 +18,23 @@ use evdevil::{
     enumerate::EnumerateHotplug,
     event::{InputEvent, Key},
 };
+use nix::{
+    errno::Errno,
+    fcntl::{FcntlArg, OFlag, fcntl},
+    sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout},
+    unistd::{pipe, read, write},
+};
+
+/// Minimum time between two activity notifications sent to the main thread.
+///
+/// Chatty devices like trackpads can produce hundreds of events per second; without this the
+/// epoll loop would wake the main thread (and reset its inactivity timer) on every single one of
+/// them.
+const NOTIFY_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// epoll token (`EpollEvent` user data) for the self-pipe that wakes the epoll loop up when a new
+/// device has been opened by the hotplug thread.
+const WAKEUP_TOKEN: u64 = u64::MAX;
 
 fn is_keyboard_or_trackpad(dev: &Evdev) -> io::Result<bool> {
     if dev.key_repeat()?.is_some() {
@@ -57,16 +77,36 @@ impl InputHandler {
         }
 
         let enumerate = evdevil::enumerate_hotplug()?;
-        let (sender, recv) = sync_channel(1);
+        let (activity_sender, activity_recv) = sync_channel(1);
+        let (dev_sender, dev_recv) = mpsc::channel();
+
+        // Self-pipe used by the hotplug thread to wake the epoll loop up as soon as a new device
+        // has been pushed onto `dev_recv`, instead of the epoll loop having to poll for it.
+        let (wakeup_read, wakeup_write) = pipe()?;
+        set_nonblocking(&wakeup_read)?;
+        set_nonblocking(&wakeup_write)?;
+
         thread::Builder::new()
             .name("hotplug".into())
             .spawn(move || {
-                hotplug_thread(enumerate, &filter, &sender);
+                hotplug_thread(enumerate, &filter, &dev_sender, &wakeup_write);
                 log::error!("hotplug thread exited unexpectedly; exiting");
                 process::exit(1);
             })?;
 
-        Ok(Self { recv })
+        thread::Builder::new()
+            .name("input".into())
+            .spawn(move || {
+                if let Err(e) = epoll_thread(wakeup_read, dev_recv, &activity_sender) {
+                    log::error!("input thread error: {e}");
+                }
+                log::error!("input thread exited unexpectedly; exiting");
+                process::exit(1);
+            })?;
+
+        Ok(Self {
+            recv: activity_recv,
+        })
     }
 
     pub fn wait_deadline(&self, deadline: Instant) -> Result<(), RecvTimeoutError> {
@@ -75,7 +115,19 @@ impl InputHandler {
     }
 }
 
-fn hotplug_thread(enumerate: EnumerateHotplug, filter: &DeviceFilter, sender: &SyncSender<()>) {
+fn set_nonblocking(fd: &OwnedFd) -> io::Result<()> {
+    let flags = fcntl(fd, FcntlArg::F_GETFL).map_err(io::Error::from)?;
+    let flags = OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK;
+    fcntl(fd, FcntlArg::F_SETFL(flags)).map_err(io::Error::from)?;
+    Ok(())
+}
+
+fn hotplug_thread(
+    enumerate: EnumerateHotplug,
+    filter: &DeviceFilter,
+    dev_sender: &mpsc::Sender<Evdev>,
+    wakeup_write: &OwnedFd,
+) {
     for res in enumerate {
         let dev = match res {
             Ok(dev) => dev,
@@ -85,14 +137,19 @@ fn hotplug_thread(enumerate: EnumerateHotplug, filter: &DeviceFilter, sender: &S
             }
         };
 
-        match maybe_open_dev(dev, &filter, sender) {
+        match maybe_open_dev(dev, filter, dev_sender, wakeup_write) {
             Ok(()) => {}
             Err(e) => log::error!("failed to query device: {e}"),
         }
     }
 }
 
-fn maybe_open_dev(dev: Evdev, filter: &DeviceFilter, sender: &SyncSender<()>) -> io::Result<()> {
+fn maybe_open_dev(
+    dev: Evdev,
+    filter: &DeviceFilter,
+    dev_sender: &mpsc::Sender<Evdev>,
+    wakeup_write: &OwnedFd,
+) -> io::Result<()> {
     let interest = match filter {
         DeviceFilter::Names(items) => items.contains(&dev.name()?),
         DeviceFilter::Auto => is_keyboard_or_trackpad(&dev)?,
@@ -104,40 +161,85 @@ fn maybe_open_dev(dev: Evdev, filter: &DeviceFilter, sender: &SyncSender<()>) ->
 
     dev.set_nonblocking(true)?;
 
-    let name = dev.name()?;
-    let sender = sender.clone();
-    thread::Builder::new()
-        .name(name.clone())
-        .spawn(move || -> anyhow::Result<()> {
-            log::info!("opened '{}' ({})", name, dev.path().display());
-            let mut buf = [InputEvent::zeroed(); 32];
-            loop {
-                dev.block_until_readable()?;
+    log::info!("opened '{}' ({})", dev.name()?, dev.path().display());
+    if dev_sender.send(dev).is_err() {
+        return Ok(());
+    }
 
-                if sender.send(()).is_err() {
-                    return Ok(());
-                }
+    // Wake the epoll loop up so it picks the new device's fd up and registers it.
+    write(wakeup_write, &[0]).ok();
+    Ok(())
+}
 
-                // Drain the kernel buffer so that we don't immediately loop again.
-                for _ in 0..16 {
-                    match dev.read_events(&mut buf) {
-                        Ok(0) => break,
+/// Owns the epoll instance that watches every opened evdev fd plus the hotplug self-pipe, and
+/// sends a single coalesced activity notification per wakeup.
+fn epoll_thread(
+    wakeup_read: OwnedFd,
+    dev_recv: mpsc::Receiver<Evdev>,
+    activity_sender: &SyncSender<()>,
+) -> anyhow::Result<()> {
+    let epoll = Epoll::new(EpollCreateFlags::empty())?;
+    epoll.add(
+        &wakeup_read,
+        EpollEvent::new(EpollFlags::EPOLLIN, WAKEUP_TOKEN),
+    )?;
+
+    let mut devices: HashMap<RawFd, Evdev> = HashMap::new();
+    let mut last_notify = Instant::now() - NOTIFY_DEBOUNCE;
+    let mut events = [EpollEvent::empty(); 16];
+
+    loop {
+        let n = epoll.wait(&mut events, EpollTimeout::NONE)?;
+
+        let mut activity = false;
+        for event in &events[..n] {
+            let token = event.data();
+            if token == WAKEUP_TOKEN {
+                // Drain the self-pipe and register every newly opened device.
+                let mut buf = [0; 64];
+                loop {
+                    match read(&wakeup_read, &mut buf) {
+                        Ok(0) | Err(Errno::EAGAIN) => break,
                         Ok(_) => {}
-                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
                         Err(e) => return Err(e.into()),
                     }
                 }
+                while let Ok(dev) = dev_recv.try_recv() {
+                    let fd = dev.as_raw_fd();
+                    epoll.add(&dev, EpollEvent::new(EpollFlags::EPOLLIN, fd as u64))?;
+                    devices.insert(fd, dev);
+                }
+                continue;
+            }
+
+            let fd = token as RawFd;
+            let Some(dev) = devices.get(&fd) else {
+                continue;
+            };
 
-                // Devices like trackpads send a lot of events, which would keep
-                // this loop unnecessarily busy, so we sleep a bit.
-                // The backlight timeout is at least 1 second, so we have to
-                // notify the main thread once every second at minimum.
-                // This may overflow the kernel buffer, but that doesn't really
-                // matter, since we only care about the presence of events,
-                // not their content.
-                thread::sleep(Duration::from_millis(350));
+            // Drain the kernel buffer non-blockingly; we only care about the presence of
+            // events, not their content.
+            let mut buf = [InputEvent::zeroed(); 32];
+            loop {
+                match dev.read_events(&mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => activity = true,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        log::error!("device error, dropping it: {e}");
+                        epoll.delete(dev).ok();
+                        devices.remove(&fd);
+                        break;
+                    }
+                }
             }
-        })?;
+        }
 
-    Ok(())
+        if activity && last_notify.elapsed() >= NOTIFY_DEBOUNCE {
+            last_notify = Instant::now();
+            if activity_sender.send(()).is_err() {
+                return Ok(());
+            }
+        }
+    }
 }
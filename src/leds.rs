@@ -1,14 +1,14 @@
 use std::{
     fs::{self, File},
     io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str,
     time::{Duration, Instant},
 };
 
 use anyhow::{Context, anyhow, bail, ensure};
 
-use crate::config::Config;
+use crate::config::{Color, Config};
 
 const BASE_PATH: &str = "/sys/class/leds/";
 const BACKLIGHT: &str = "kbd_backlight";
@@ -29,7 +29,7 @@ struct Led {
 }
 
 impl Led {
-    fn open(name: String, target_brightness: u8) -> anyhow::Result<Self> {
+    fn open(name: String, target_brightness: u8, color: Option<Color>) -> anyhow::Result<Self> {
         let mut base_path = PathBuf::from(BASE_PATH);
         base_path.push(&name);
 
@@ -45,6 +45,18 @@ impl Led {
         let target_brightness =
             (target_brightness as f32 / 100.0 * max_brightness as f32).round() as u32;
         log::debug!("abs. target brightness: {target_brightness}");
+
+        if let Some(color) = color {
+            // `multi_intensity` sets the color ratio relative to `max_brightness`; the overall
+            // dimming (including the fade) is handled by `brightness` alone, so this is written
+            // once and then left alone.
+            if let Err(e) = set_multi_intensity(&base_path, color, max_brightness) {
+                log::warn!(
+                    "'{name}' has a `color` configured, but {e}; keyboard will only be dimmed, not colored"
+                );
+            }
+        }
+
         Ok(Self {
             name,
             file,
@@ -72,6 +84,64 @@ impl Led {
     }
 }
 
+/// A channel of a `red green blue`-style `multi_intensity` sysfs file, as found on RGB/multi-color
+/// keyboard backlights.
+enum Channel {
+    Red,
+    Green,
+    Blue,
+    /// A channel this daemon doesn't know how to map a `Color` onto (eg. `white`); kept dark.
+    Unknown,
+}
+
+impl Channel {
+    fn parse(name: &str) -> Self {
+        match name {
+            "red" => Self::Red,
+            "green" => Self::Green,
+            "blue" => Self::Blue,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Scales this channel's component of `color` against `max_brightness`, i.e. computes the
+    /// channel's `multi_intensity` value for a fully-saturated `color`.
+    fn scale(&self, color: Color, max_brightness: u32) -> u32 {
+        let component = match self {
+            Self::Red => color.0[0],
+            Self::Green => color.0[1],
+            Self::Blue => color.0[2],
+            Self::Unknown => 0,
+        };
+        (component as f32 / u8::MAX as f32 * max_brightness as f32).round() as u32
+    }
+}
+
+/// Writes `color`, scaled against `max_brightness`, to `multi_intensity` as a one-off. The actual
+/// dimming (and fading) is handled by the `brightness` file, so this never needs to be touched
+/// again afterwards.
+fn set_multi_intensity(base_path: &Path, color: Color, max_brightness: u32) -> anyhow::Result<()> {
+    let index = fs::read_to_string(base_path.join("multi_index"))
+        .context("no `multi_index` (not a multi-color LED)")?;
+    let channels: Vec<Channel> = index.split_whitespace().map(Channel::parse).collect();
+    ensure!(!channels.is_empty(), "`multi_index` is empty");
+
+    let mut buf = [0; 64];
+    let mut writer = &mut buf[..];
+    for (i, channel) in channels.iter().enumerate() {
+        if i > 0 {
+            write!(writer, " ")?;
+        }
+        write!(writer, "{}", channel.scale(color, max_brightness))?;
+    }
+    let remaining = writer.len();
+    let n = buf.len() - remaining;
+
+    fs::write(base_path.join("multi_intensity"), &buf[..n])
+        .context("failed to write `multi_intensity`")?;
+    Ok(())
+}
+
 impl Leds {
     pub fn from_config(conf: &Config) -> anyhow::Result<Self> {
         if conf.leds.is_empty() {
@@ -88,6 +158,7 @@ impl Leds {
                 Led::open(
                     led.name.clone(),
                     led.brightness.unwrap_or(conf.general.brightness).raw(),
+                    led.color,
                 )
             })
             .collect::<anyhow::Result<_>>()?;
@@ -111,7 +182,7 @@ impl Leds {
                 continue;
             }
 
-            leds.push(Led::open(name.into(), conf.general.brightness.raw())?);
+            leds.push(Led::open(name.into(), conf.general.brightness.raw(), None)?);
         }
 
         if leds.is_empty() {
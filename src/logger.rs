@@ -1,6 +1,11 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    env,
+    io::stderr,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use log::{Level, LevelFilter, Log};
+use nix::sys::stat::fstat;
 
 pub fn init(verbose: bool) {
     log::set_logger(&LOGGER).expect("failed to set logger");
@@ -13,14 +18,45 @@ pub fn init(verbose: bool) {
     if verbose {
         LOGGER.verbose.store(true, Ordering::Relaxed);
     }
+    if stderr_is_journal_stream() {
+        LOGGER.journal.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether log output is using the journald-style `<N>` priority prefixes instead of the
+/// human-readable `LEVEL` column.
+pub fn is_journal_mode() -> bool {
+    LOGGER.journal.load(Ordering::Relaxed)
+}
+
+/// Checks `$JOURNAL_STREAM` against stderr's device/inode, the same way sd-daemon's
+/// `sd_journal_stream_fd()` does, to tell whether stderr is connected to the systemd journal.
+fn stderr_is_journal_stream() -> bool {
+    let Ok(stream) = env::var("JOURNAL_STREAM") else {
+        return false;
+    };
+    let Some((dev, inode)) = stream.split_once(':') else {
+        return false;
+    };
+    let (Ok(dev), Ok(inode)) = (dev.parse::<u64>(), inode.parse::<u64>()) else {
+        return false;
+    };
+
+    let Ok(stat) = fstat(&stderr()) else {
+        return false;
+    };
+    u64::from(stat.st_dev) == dev && u64::from(stat.st_ino) == inode
 }
 
 static LOGGER: Logger = Logger {
     verbose: AtomicBool::new(false),
+    journal: AtomicBool::new(false),
 };
 
 struct Logger {
     verbose: AtomicBool,
+    /// Whether stderr is connected to the systemd journal.
+    journal: AtomicBool,
 }
 
 impl Log for Logger {
@@ -34,13 +70,32 @@ impl Log for Logger {
     }
 
     fn log(&self, record: &log::Record) {
-        eprintln!(
-            "{:>5} [{}] {}",
-            record.level(),
-            record.target(),
-            record.args()
-        );
+        if self.journal.load(Ordering::Relaxed) {
+            eprintln!(
+                "{}[{}] {}",
+                priority_prefix(record.level()),
+                record.target(),
+                record.args()
+            );
+        } else {
+            eprintln!(
+                "{:>5} [{}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
     }
 
     fn flush(&self) {}
 }
+
+/// Returns the sd-daemon syslog priority prefix (see `sd-daemon(3)`) for `level`.
+fn priority_prefix(level: Level) -> &'static str {
+    match level {
+        Level::Error => "<3>",
+        Level::Warn => "<4>",
+        Level::Info => "<6>",
+        Level::Debug | Level::Trace => "<7>",
+    }
+}
@@ -0,0 +1,121 @@
+//! Optional integration with systemd-logind: lets the daemon pause LED control while the session
+//! isn't the one being looked at, and re-assert LED state after resuming from suspend.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::OwnedObjectPath,
+};
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+
+/// A logind event relevant to whether LED control should currently be active.
+pub enum SessionEvent {
+    /// The session's `Active` property changed (it became foreground/background).
+    Active(bool),
+    /// The system just resumed from suspend/hibernate.
+    Resumed,
+}
+
+/// Watches the current logind session and the manager's sleep signal on background threads.
+pub struct Session {
+    recv: Receiver<SessionEvent>,
+}
+
+impl Session {
+    /// Connects to the system bus and starts watching logind for events.
+    ///
+    /// Returns the new `Session` together with the session's current `Active` value, so the
+    /// caller can seed its own state instead of assuming the session starts out active.
+    pub fn connect() -> anyhow::Result<(Self, bool)> {
+        let connection = Connection::system()?;
+        let session_path = current_session_path(&connection)?;
+
+        let session_proxy = Proxy::new(
+            &connection,
+            DESTINATION,
+            session_path,
+            "org.freedesktop.login1.Session",
+        )?;
+        let initial_active: bool = session_proxy.get_property("Active")?;
+
+        let (sender, recv) = mpsc::channel();
+
+        let active_sender = sender.clone();
+        thread::Builder::new()
+            .name("logind-active".into())
+            .spawn(move || {
+                if let Err(e) = watch_active(&session_proxy, &active_sender) {
+                    log::error!("logind `Active` watcher exited: {e}");
+                }
+            })?;
+
+        thread::Builder::new()
+            .name("logind-sleep".into())
+            .spawn(move || {
+                if let Err(e) = watch_sleep(&connection, &sender) {
+                    log::error!("logind `PrepareForSleep` watcher exited: {e}");
+                }
+            })?;
+
+        Ok((Self { recv }, initial_active))
+    }
+
+    /// Returns the next pending event without blocking, if any.
+    pub fn try_recv(&self) -> Option<SessionEvent> {
+        match self.recv.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+fn current_session_path(connection: &Connection) -> anyhow::Result<OwnedObjectPath> {
+    let manager = Proxy::new(
+        connection,
+        DESTINATION,
+        MANAGER_PATH,
+        "org.freedesktop.login1.Manager",
+    )?;
+    // A PID of 0 resolves to the caller's own session.
+    let path: OwnedObjectPath = manager.call("GetSessionByPID", &(0u32,))?;
+    Ok(path)
+}
+
+fn watch_active(proxy: &Proxy<'static>, sender: &mpsc::Sender<SessionEvent>) -> anyhow::Result<()> {
+    for changed in proxy.receive_property_changed::<bool>("Active") {
+        let active = changed.get()?;
+        log::debug!("logind session Active = {active}");
+        if sender.send(SessionEvent::Active(active)).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+fn watch_sleep(connection: &Connection, sender: &mpsc::Sender<SessionEvent>) -> anyhow::Result<()> {
+    let proxy = Proxy::new(
+        connection,
+        DESTINATION,
+        MANAGER_PATH,
+        "org.freedesktop.login1.Manager",
+    )?;
+
+    for signal in proxy.receive_signal("PrepareForSleep")? {
+        let (going_to_sleep,): (bool,) = signal.body().deserialize()?;
+        if going_to_sleep {
+            continue;
+        }
+
+        log::debug!("resumed from suspend");
+        if sender.send(SessionEvent::Resumed).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
@@ -2,8 +2,10 @@ mod config;
 mod input;
 mod leds;
 mod logger;
+mod logind;
 
 use std::{
+    cmp,
     path::PathBuf,
     time::{Duration, Instant},
 };
@@ -15,8 +17,13 @@ use crate::{
     config::{Brightness, Config},
     input::{DeviceFilter, InputHandler},
     leds::Leds,
+    logind::{Session, SessionEvent},
 };
 
+/// How often to poll the logind session for changes while `session_integration` is enabled, so
+/// that pausing/resuming LED control doesn't have to wait for the backlight timeout to elapse.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Parser)]
 #[command(version, about)]
 struct Args {
@@ -36,6 +43,14 @@ struct Args {
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     logger::init(args.verbose);
+    log::debug!(
+        "log output format: {}",
+        if logger::is_journal_mode() {
+            "journald"
+        } else {
+            "pretty"
+        }
+    );
 
     if args.config.is_some() && args.brightness.is_some() {
         bail!(
@@ -67,7 +82,10 @@ fn main() -> anyhow::Result<()> {
 struct Service {
     leds: Leds,
     input: InputHandler,
+    session: Option<Session>,
     state: bool,
+    /// Whether the logind session is currently active; always `true` when `session` is `None`.
+    session_active: bool,
     last_change: Instant,
     timeout: Duration,
 }
@@ -84,10 +102,24 @@ impl Service {
         let timeout = Duration::from_secs(config.general.timeout.get().into());
         let input = InputHandler::spawn(filter)?;
 
+        let (session, session_active) = if config.general.session_integration {
+            match Session::connect() {
+                Ok((session, active)) => (Some(session), active),
+                Err(e) => {
+                    log::error!("failed to connect to logind, session_integration disabled: {e}");
+                    (None, true)
+                }
+            }
+        } else {
+            (None, true)
+        };
+
         Ok(Self {
             leds,
             input,
+            session,
             state: false,
+            session_active,
             last_change: Instant::now(),
             timeout,
         })
@@ -95,19 +127,81 @@ impl Service {
 
     fn run(&mut self) -> ! {
         loop {
-            let new_state = match self.input.wait_deadline(self.last_change + self.timeout) {
-                Ok(_) => true,
-                Err(_) => false,
+            self.poll_session();
+
+            let real_deadline = self.last_change + self.timeout;
+            let wait_deadline = match &self.session {
+                // Wake up early enough for `poll_session` to notice session changes promptly,
+                // without that early wakeup counting as the inactivity timeout elapsing.
+                Some(_) => cmp::min(real_deadline, Instant::now() + SESSION_POLL_INTERVAL),
+                None => real_deadline,
             };
-            self.last_change = Instant::now();
 
-            if self.state != new_state {
-                self.state = new_state;
-                log::info!("{}", if new_state { "ON" } else { "OFF" });
-                if let Err(e) = self.leds.set_state(new_state) {
-                    log::error!("failed to set LED brightness: {e}");
+            match self.input.wait_deadline(wait_deadline) {
+                Ok(_) => {
+                    self.last_change = Instant::now();
+                    self.set_state(true);
+                }
+                Err(_) if Instant::now() < real_deadline => {
+                    // Only the artificial session-poll deadline elapsed; go around again without
+                    // touching `last_change` or the LED state.
+                }
+                Err(_) => {
+                    self.last_change = Instant::now();
+                    self.set_state(false);
+                }
+            }
+        }
+    }
+
+    fn set_state(&mut self, new_state: bool) {
+        if self.state != new_state {
+            self.state = new_state;
+            log::info!("{}", if new_state { "ON" } else { "OFF" });
+            self.apply_state(false);
+        }
+    }
+
+    /// Drains pending logind events and reacts to them.
+    fn poll_session(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        let mut events = Vec::new();
+        while let Some(event) = session.try_recv() {
+            events.push(event);
+        }
+
+        for event in events {
+            match event {
+                SessionEvent::Active(active) => {
+                    log::info!("session {}", if active { "active" } else { "inactive" });
+                    self.session_active = active;
+                    if active {
+                        self.apply_state(false);
+                    }
+                }
+                SessionEvent::Resumed => {
+                    // The kernel/firmware may have reset the LED brightness across suspend, so
+                    // this has to run even while the session is inactive, or the backlight could
+                    // come back lit for nobody to see.
+                    log::info!("resumed from suspend, re-asserting LED state");
+                    self.apply_state(true);
                 }
             }
         }
     }
+
+    /// Re-runs the LED fade for the current `state`. Unless `force` is set, this is a no-op while
+    /// the session is paused.
+    fn apply_state(&mut self, force: bool) {
+        if !force && !self.session_active {
+            return;
+        }
+
+        if let Err(e) = self.leds.set_state(self.state) {
+            log::error!("failed to set LED brightness: {e}");
+        }
+    }
 }